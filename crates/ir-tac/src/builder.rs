@@ -0,0 +1,106 @@
+use primitive_types::U256;
+
+use crate::instruction::{self, Instruction, Operator};
+use crate::symbol::{SymbolTable, Type};
+
+/// A fluent, correct-by-construction way to author 3AC programs.
+///
+/// Wraps a `SymbolTable` and reuses the same stack-bookkeeping helpers
+/// `translate` itself is built from, so callers never have to remember the
+/// decrement/load/increment ordering by hand.
+pub struct Builder<'a> {
+    symbol_table: &'a mut SymbolTable,
+    instructions: Vec<Instruction>,
+}
+
+impl<'a> Builder<'a> {
+    pub fn new(symbol_table: &'a mut SymbolTable) -> Self {
+        Builder {
+            symbol_table,
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Push a constant value onto the stack.
+    pub fn push_const(mut self, value: U256) -> Self {
+        let constant = self.symbol_table.constant(value, Some(Type::Bytes(32)));
+        let push = instruction::stack_push(self.symbol_table, constant);
+
+        self.instructions.push(push.assign);
+        self.instructions.push(push.increment);
+        self
+    }
+
+    /// Pop two values, apply `operator`, and push the result. Shift operators
+    /// take their operands in the same order as `translate` does for
+    /// SHL/SHR/SAR.
+    pub fn binary(mut self, operator: Operator) -> Self {
+        let lowered = match operator {
+            Operator::ShiftLeft | Operator::ShiftRight | Operator::ShiftArithmeticRight => {
+                instruction::lower_shift(self.symbol_table, operator)
+            }
+            _ => instruction::lower_binary(self.symbol_table, operator),
+        };
+        self.instructions.extend(lowered);
+        self
+    }
+
+    /// Duplicate the `n`-th element from the top of the stack (as in
+    /// DUP1..DUP16).
+    pub fn dup(mut self, n: u8) -> Self {
+        let peek = instruction::stack_peek(self.symbol_table, n);
+        let push = instruction::stack_push(self.symbol_table, peek.load.target_address());
+
+        self.instructions.push(peek.index);
+        self.instructions.push(peek.load);
+        self.instructions.push(push.assign);
+        self.instructions.push(push.increment);
+        self
+    }
+
+    /// Pop the top of the stack and branch to it unconditionally.
+    pub fn jump(mut self) -> Self {
+        let target = instruction::stack_pop(self.symbol_table);
+        let address = target.load.target_address();
+
+        self.instructions.push(target.decrement);
+        self.instructions.push(target.load);
+        self.instructions
+            .push(Instruction::UncoditionalBranch { target: address });
+        self
+    }
+
+    /// Consume the builder, returning the instructions emitted so far.
+    pub fn finish(self) -> Vec<Instruction> {
+        self.instructions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use primitive_types::U256;
+
+    use crate::instruction::{Instruction, Operator};
+
+    use super::Builder;
+
+    #[test]
+    fn push_const_binary_jump_chain_builds_without_manual_bookkeeping() {
+        let mut symbol_table = Default::default();
+
+        let program = Builder::new(&mut symbol_table)
+            .push_const(U256::from(1))
+            .push_const(U256::from(2))
+            .binary(Operator::Add)
+            .push_const(U256::from(5))
+            .jump()
+            .finish();
+
+        assert!(program
+            .iter()
+            .any(|instruction| matches!(instruction, Instruction::BinaryAssign { operator: Operator::Add, .. })));
+        assert!(program
+            .iter()
+            .any(|instruction| matches!(instruction, Instruction::UncoditionalBranch { .. })));
+    }
+}
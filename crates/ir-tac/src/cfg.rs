@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use evmil::bytecode::Instruction as EvmInstruction;
+use primitive_types::U256;
+
+use crate::instruction::{self, Instruction};
+use crate::symbol::{Address, Global, SymbolTable};
+
+/// The label of a basic block, identified by the byte offset of its leading
+/// instruction in the original EVM bytecode.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Label(pub u16);
+
+impl std::fmt::Display for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "L{}", self.0)
+    }
+}
+
+/// A straight-line run of 3AC instructions with no internal control flow.
+#[derive(Debug)]
+pub struct BasicBlock {
+    pub label: Label,
+    pub instructions: Vec<Instruction>,
+}
+
+/// How control may leave the end of a `BasicBlock`.
+#[derive(Debug)]
+pub enum Edge {
+    /// The JUMP/JUMPI target resolved to the `JUMPDEST` at this label.
+    Branch(Label),
+    /// The target could not be traced back to a constant `PUSH`.
+    Dynamic,
+    /// A `checked`-mode stack underflow/overflow guard failed.
+    Revert,
+}
+
+/// The control-flow graph of a translated program.
+#[derive(Debug)]
+pub struct Cfg {
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<(Label, Edge)>,
+}
+
+/// Translate a full EVM bytecode stream, paired with each instruction's byte
+/// offset, into a `Cfg`.
+///
+/// The instruction stream is split into basic blocks at every `JUMPDEST` and
+/// after every branch or `RETURN`. A `JUMP`/`JUMPI` target is resolved to the
+/// `Label` of the block whose `JUMPDEST` sits at that byte offset when the
+/// value being jumped to was produced by the immediately preceding `PUSH`;
+/// any other target is recorded as `Edge::Dynamic`.
+///
+/// `checked` is forwarded to `instruction::translate` to control whether
+/// stack underflow/overflow guards are emitted for each opcode. Each guard's
+/// `ConditionalBranch` to `Global::Revert` also ends the block it appears in
+/// — a block never has a branch buried inside it — with the continuation
+/// opening under a synthetic label (one past the highest byte offset in
+/// `program`, so it can never collide with a real `JUMPDEST`).
+pub fn build_cfg(
+    program: &[(u16, EvmInstruction)],
+    symbol_table: &mut SymbolTable,
+    checked: bool,
+) -> Cfg {
+    let jumpdests: HashMap<U256, Label> = program
+        .iter()
+        .filter(|(_, opcode)| matches!(opcode, EvmInstruction::JUMPDEST))
+        .map(|(offset, _)| (U256::from(*offset), Label(*offset)))
+        .collect();
+
+    let mut blocks = Vec::new();
+    let mut edges = Vec::new();
+
+    let mut label = program.first().map(|(offset, _)| Label(*offset)).unwrap_or(Label(0));
+    let mut instructions = Vec::new();
+    let mut last_push: Option<U256> = None;
+
+    let mut next_synthetic_label = program.iter().map(|(offset, _)| *offset).max().map_or(0, |max| max + 1);
+
+    for (offset, opcode) in program.iter() {
+        // A `JUMPDEST` only opens a new block if `label` doesn't already
+        // point at it. It already does when a branch/`RETURN` advanced
+        // `label` to this exact offset as its fallthrough target (the
+        // common if/else join shape) — splitting there too would push a
+        // second, empty block under the same label.
+        if matches!(opcode, EvmInstruction::JUMPDEST) && label != Label(*offset) {
+            blocks.push(BasicBlock {
+                label,
+                instructions: std::mem::take(&mut instructions),
+            });
+            label = Label(*offset);
+        }
+
+        for lowered in instruction::translate(opcode, symbol_table, checked) {
+            // `ConditionalBranch` is also how a correctly-lowered `JUMPI`
+            // exits a block (resolved against `jumpdests` below, same as
+            // `JUMP`) — only a branch that actually targets `Global::Revert`
+            // is a stack guard.
+            let is_guard = match &lowered {
+                Instruction::ConditionalBranch { target, .. } => target.address == Address::Label(Global::Revert),
+                _ => false,
+            };
+            instructions.push(lowered);
+
+            if is_guard {
+                edges.push((label, Edge::Revert));
+                blocks.push(BasicBlock {
+                    label,
+                    instructions: std::mem::take(&mut instructions),
+                });
+                label = Label(next_synthetic_label);
+                next_synthetic_label += 1;
+            }
+        }
+
+        match opcode {
+            EvmInstruction::JUMP | EvmInstruction::JUMPI => {
+                let target = last_push.and_then(|value| jumpdests.get(&value).copied());
+                let edge = match target {
+                    Some(destination) => Edge::Branch(destination),
+                    None => Edge::Dynamic,
+                };
+
+                edges.push((label, edge));
+                blocks.push(BasicBlock {
+                    label,
+                    instructions: std::mem::take(&mut instructions),
+                });
+                label = Label(offset + 1);
+            }
+
+            EvmInstruction::RETURN => {
+                blocks.push(BasicBlock {
+                    label,
+                    instructions: std::mem::take(&mut instructions),
+                });
+                label = Label(offset + 1);
+            }
+
+            _ => {}
+        }
+
+        last_push = match opcode {
+            EvmInstruction::PUSH(bytes) => Some(U256::from_big_endian(bytes)),
+            _ => None,
+        };
+    }
+
+    // A trailing empty block is only meaningful if `label` is itself a real
+    // `JUMPDEST` (a legitimate, possibly-empty jump target); otherwise it's
+    // just the synthetic one-past-the-end offset left over from the last
+    // branch/`RETURN` and would add a block nothing can ever reach.
+    if !instructions.is_empty() || jumpdests.values().any(|destination| *destination == label) {
+        blocks.push(BasicBlock { label, instructions });
+    }
+
+    Cfg { blocks, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use evmil::bytecode;
+    use primitive_types::U256;
+
+    use super::{build_cfg, Edge};
+
+    #[test]
+    fn static_jump_resolves_to_the_jumpdest_label() {
+        let mut symbol_table = Default::default();
+
+        let program = vec![
+            (0u16, bytecode::Instruction::PUSH(vec![0x05])),
+            (2, bytecode::Instruction::JUMP),
+            (3, bytecode::Instruction::JUMPDEST),
+            (4, bytecode::Instruction::JUMPDEST),
+            (5, bytecode::Instruction::JUMPDEST),
+        ];
+
+        let cfg = build_cfg(&program, &mut symbol_table, false);
+
+        // Each JUMPDEST is its own addressable target, even if empty: 4
+        // blocks at offsets 0, 3, 4 and 5, none of them duplicated.
+        assert_eq!(cfg.blocks.len(), 4);
+        assert_eq!(cfg.edges.len(), 1);
+        assert!(matches!(
+            cfg.edges[0].1,
+            Edge::Branch(super::Label(offset)) if offset == 5
+        ));
+        assert!(cfg.blocks.iter().any(|block| block.label == super::Label(5)));
+
+        // sanity: the resolved target offset matches the pushed constant
+        let pushed = match &program[0].1 {
+            bytecode::Instruction::PUSH(bytes) => U256::from_big_endian(bytes),
+            _ => unreachable!(),
+        };
+        let resolved = match cfg.edges[0].1 {
+            Edge::Branch(super::Label(offset)) => U256::from(offset),
+            _ => unreachable!(),
+        };
+        assert_eq!(resolved, pushed);
+    }
+
+    #[test]
+    fn jumpdest_immediately_after_a_jump_does_not_duplicate_the_block() {
+        let mut symbol_table = Default::default();
+
+        // The common if/else join shape: JUMP straight to a JUMPDEST with
+        // code right behind it, rather than to a run of bare JUMPDESTs.
+        let program = vec![
+            (0u16, bytecode::Instruction::PUSH(vec![0x03])),
+            (2, bytecode::Instruction::JUMP),
+            (3, bytecode::Instruction::JUMPDEST),
+            (4, bytecode::Instruction::PUSH(vec![0x01])),
+            (6, bytecode::Instruction::POP),
+        ];
+
+        let cfg = build_cfg(&program, &mut symbol_table, false);
+
+        assert_eq!(cfg.blocks.len(), 2);
+        assert_eq!(cfg.blocks[1].label, super::Label(3));
+        assert!(!cfg.blocks[1].instructions.is_empty());
+    }
+
+    #[test]
+    fn checked_build_splits_blocks_on_the_stack_guard() {
+        let mut symbol_table = Default::default();
+
+        // POP nets exactly one guard (underflow only, since it has no net
+        // push): the ConditionalBranch it emits must end the block it's in
+        // rather than live buried inside a bigger one, with a Revert edge
+        // recorded for it.
+        let program = vec![(0u16, bytecode::Instruction::POP)];
+
+        let cfg = build_cfg(&program, &mut symbol_table, true);
+
+        let revert_edges = cfg.edges.iter().filter(|(_, edge)| matches!(edge, Edge::Revert)).count();
+        assert_eq!(revert_edges, 1);
+
+        // No block contains a ConditionalBranch anywhere but, if at all, as
+        // its own exit — i.e. never followed by further instructions.
+        for block in &cfg.blocks {
+            let guard_position = block
+                .instructions
+                .iter()
+                .position(|instruction| matches!(instruction, super::Instruction::ConditionalBranch { .. }));
+
+            if let Some(position) = guard_position {
+                assert_eq!(position, block.instructions.len() - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn checked_jumpi_is_resolved_as_a_branch_not_mislabeled_as_a_revert() {
+        let mut symbol_table = Default::default();
+
+        // PUSH condition; PUSH target; JUMPI; ...; JUMPDEST at the target.
+        // Checked mode also guards both PUSHes (overflow) and JUMPI's two
+        // pops (underflow), so this program carries both a real conditional
+        // branch (to the JUMPDEST) and synthetic guard branches (to Revert)
+        // — they must not collapse into the same edge kind.
+        let program = vec![
+            (0u16, bytecode::Instruction::PUSH(vec![0x00])),
+            (2, bytecode::Instruction::PUSH(vec![0x05])),
+            (4, bytecode::Instruction::JUMPI),
+            (5, bytecode::Instruction::JUMPDEST),
+        ];
+
+        let cfg = build_cfg(&program, &mut symbol_table, true);
+
+        let revert_edges = cfg.edges.iter().filter(|(_, edge)| matches!(edge, Edge::Revert)).count();
+        let branch_edges = cfg
+            .edges
+            .iter()
+            .filter(|(_, edge)| matches!(edge, Edge::Branch(super::Label(5))))
+            .count();
+
+        // 1 overflow guard per PUSH, 1 underflow guard for JUMPI's two pops.
+        assert_eq!(revert_edges, 3);
+        assert_eq!(branch_edges, 1);
+    }
+
+    #[test]
+    fn dynamic_jump_is_left_unresolved() {
+        let mut symbol_table = Default::default();
+
+        let program = vec![
+            (0u16, bytecode::Instruction::JUMPDEST),
+            (1, bytecode::Instruction::JUMP),
+        ];
+
+        let cfg = build_cfg(&program, &mut symbol_table, false);
+
+        assert!(matches!(cfg.edges[0].1, Edge::Dynamic));
+    }
+}
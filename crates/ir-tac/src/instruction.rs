@@ -2,7 +2,7 @@ use evmil::bytecode::Instruction as EvmInstruction;
 use primitive_types::U256;
 use std::fmt::Write;
 
-use crate::symbol::{Global, Symbol, SymbolTable, Type};
+use crate::symbol::{Global, Provenance, Symbol, SymbolTable, Type};
 
 #[derive(PartialEq, Debug)]
 pub enum Instruction {
@@ -51,7 +51,7 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    fn target_address(&self) -> Symbol {
+    pub(crate) fn target_address(&self) -> Symbol {
         match self {
             Instruction::Copy { x, .. } => *x,
             Instruction::IndexedAssign { x, .. } => *x,
@@ -136,19 +136,29 @@ pub enum Operator {
     ShiftArithmeticRight,
 }
 
-struct StackPop {
-    decrement: Instruction,
-    load: Instruction,
+pub(crate) struct StackPop {
+    pub(crate) decrement: Instruction,
+    pub(crate) load: Instruction,
 }
 
 /// Pop a value from the stack.
 ///
-/// Returns 2 `Instruction`: Decrementing the stack pointer and the value copy.
-fn stack_pop(symbol_table: &mut SymbolTable) -> StackPop {
+/// Returns 2 `Instruction`: Decrementing the stack pointer and the value
+/// copy. The loaded temporary is tagged with the provenance of whatever was
+/// pushed into that slot (see `SymbolTable::shadow_pop`), so a tag set at
+/// push time (e.g. `Provenance::CallData` from `CALLDATALOAD`) survives the
+/// round trip instead of being erased.
+pub(crate) fn stack_pop(symbol_table: &mut SymbolTable) -> StackPop {
     let decrement = decrement_stack_height(symbol_table);
 
+    let provenance = symbol_table.shadow_pop();
+    let mut loaded = symbol_table.temporary(None);
+    if let Some(provenance) = provenance {
+        loaded = loaded.with_provenance(provenance);
+    }
+
     let load = Instruction::IndexedCopy {
-        x: symbol_table.temporary(None),
+        x: loaded,
         y: symbol_table.global(Global::Stack),
         index: symbol_table.global(Global::StackHeight),
     };
@@ -157,7 +167,7 @@ fn stack_pop(symbol_table: &mut SymbolTable) -> StackPop {
 }
 
 /// Decrease the stack height by one.
-fn decrement_stack_height(symbol_table: &mut SymbolTable) -> Instruction {
+pub(crate) fn decrement_stack_height(symbol_table: &mut SymbolTable) -> Instruction {
     Instruction::BinaryAssign {
         x: symbol_table.global(Global::StackHeight),
         y: symbol_table.global(Global::StackHeight),
@@ -166,15 +176,20 @@ fn decrement_stack_height(symbol_table: &mut SymbolTable) -> Instruction {
     }
 }
 
-struct StackPush {
-    assign: Instruction,
-    increment: Instruction,
+pub(crate) struct StackPush {
+    pub(crate) assign: Instruction,
+    pub(crate) increment: Instruction,
 }
 
 /// Push a `value` to the stack.
 ///
 /// Returns 2 `Instruction`: the value assign and the stack height increase.
-fn stack_push(symbol_table: &mut SymbolTable, value: Symbol) -> StackPush {
+/// Also records `value`'s provenance on the shadow stack (see
+/// `SymbolTable::shadow_push`) so a later `stack_pop`/`stack_peek` of this
+/// slot can recover it.
+pub(crate) fn stack_push(symbol_table: &mut SymbolTable, value: Symbol) -> StackPush {
+    symbol_table.shadow_push(value.provenance);
+
     let assign = Instruction::IndexedAssign {
         x: symbol_table.global(Global::Stack),
         index: symbol_table.global(Global::StackHeight),
@@ -186,7 +201,7 @@ fn stack_push(symbol_table: &mut SymbolTable, value: Symbol) -> StackPush {
 }
 
 /// Increment the stack height by one.
-fn increment_stack_height(symbol_table: &mut SymbolTable) -> Instruction {
+pub(crate) fn increment_stack_height(symbol_table: &mut SymbolTable) -> Instruction {
     Instruction::BinaryAssign {
         x: symbol_table.global(Global::StackHeight),
         y: symbol_table.global(Global::StackHeight),
@@ -195,12 +210,379 @@ fn increment_stack_height(symbol_table: &mut SymbolTable) -> Instruction {
     }
 }
 
-/// Lower an EVM instruction into corresponding 3AC instructions.
-pub fn translate(opcode: &EvmInstruction, symbol_table: &mut SymbolTable) -> Vec<Instruction> {
+pub(crate) struct StackPeek {
+    pub(crate) index: Instruction,
+    pub(crate) load: Instruction,
+}
+
+/// Peek the `n`-th element from the top of the stack (1-indexed, as in
+/// DUP1..DUP16) without removing it.
+///
+/// Returns 2 `Instruction`s: computing the index and the value copy. Mirrors
+/// `Stack::peek(no_from_top)` returning `stack[height - no_from_top - 1]`,
+/// with `no_from_top = n - 1`. The loaded temporary is tagged with the
+/// peeked slot's provenance (see `SymbolTable::shadow_peek`), the same way
+/// `stack_pop` recovers it.
+pub(crate) fn stack_peek(symbol_table: &mut SymbolTable, n: u8) -> StackPeek {
+    let index_symbol = symbol_table.temporary(Some(Type::Int(4)));
+    let index = Instruction::BinaryAssign {
+        x: index_symbol,
+        y: symbol_table.global(Global::StackHeight),
+        operator: Operator::Sub,
+        z: symbol_table.constant(U256::from(n), Some(Type::Int(4))),
+    };
+
+    let provenance = symbol_table.shadow_peek(usize::from(n - 1));
+    let mut loaded = symbol_table.temporary(None);
+    if let Some(provenance) = provenance {
+        loaded = loaded.with_provenance(provenance);
+    }
+
+    let load = Instruction::IndexedCopy {
+        x: loaded,
+        y: symbol_table.global(Global::Stack),
+        index: index_symbol,
+    };
+
+    StackPeek { index, load }
+}
+
+/// Swap the top stack element with the one `n` elements below it (as in
+/// SWAP1..SWAP16), leaving `StackHeight` unchanged.
+///
+/// Mirrors `Stack::swap_with_top(no_from_top)`. Exchanges `stack[height-1]`
+/// with `stack[height-1-n]` through a scratch temporary: two `IndexedCopy`s
+/// read both slots, two `IndexedAssign`s write them back swapped.
+pub(crate) fn stack_swap(symbol_table: &mut SymbolTable, n: u8) -> Vec<Instruction> {
+    symbol_table.shadow_swap(usize::from(n));
+
+    let top_index = symbol_table.temporary(Some(Type::Int(4)));
+    let compute_top_index = Instruction::BinaryAssign {
+        x: top_index,
+        y: symbol_table.global(Global::StackHeight),
+        operator: Operator::Sub,
+        z: symbol_table.constant(U256::one(), Some(Type::Int(4))),
+    };
+
+    let other_index = symbol_table.temporary(Some(Type::Int(4)));
+    let compute_other_index = Instruction::BinaryAssign {
+        x: other_index,
+        y: symbol_table.global(Global::StackHeight),
+        operator: Operator::Sub,
+        z: symbol_table.constant(U256::from(n + 1), Some(Type::Int(4))),
+    };
+
+    let top_value = Instruction::IndexedCopy {
+        x: symbol_table.temporary(None),
+        y: symbol_table.global(Global::Stack),
+        index: top_index,
+    };
+    let top_symbol = top_value.target_address();
+
+    let other_value = Instruction::IndexedCopy {
+        x: symbol_table.temporary(None),
+        y: symbol_table.global(Global::Stack),
+        index: other_index,
+    };
+    let other_symbol = other_value.target_address();
+
+    let move_other_to_top = Instruction::IndexedAssign {
+        x: symbol_table.global(Global::Stack),
+        index: top_index,
+        y: other_symbol,
+    };
+
+    let move_top_to_other = Instruction::IndexedAssign {
+        x: symbol_table.global(Global::Stack),
+        index: other_index,
+        y: top_symbol,
+    };
+
+    vec![
+        compute_top_index,
+        compute_other_index,
+        top_value,
+        other_value,
+        move_other_to_top,
+        move_top_to_other,
+    ]
+}
+
+/// Pop `a` (top) then `b`, compute `a operator b` into a fresh temporary and
+/// push the result.
+///
+/// This mirrors the EVM interpreter's binary-op dispatch: the element popped
+/// first is the left-hand operand, so non-commutative operators such as SUB
+/// and DIV come out in the correct order. SHL/SHR/SAR put their shift amount
+/// on top instead of the value, so they use `lower_shift`, not this.
+pub(crate) fn lower_binary(symbol_table: &mut SymbolTable, operator: Operator) -> Vec<Instruction> {
+    let a = stack_pop(symbol_table);
+    let b = stack_pop(symbol_table);
+
+    let x = symbol_table.temporary(None).with_provenance(Provenance::Computed);
+    let compute = Instruction::BinaryAssign {
+        x,
+        y: a.load.target_address(),
+        operator,
+        z: b.load.target_address(),
+    };
+
+    let push = stack_push(symbol_table, x);
+
+    vec![
+        a.decrement,
+        a.load,
+        b.decrement,
+        b.load,
+        compute,
+        push.assign,
+        push.increment,
+    ]
+}
+
+/// Pop `shift` (top) then `value`, compute `value operator shift` into a
+/// fresh temporary and push the result.
+///
+/// SHL/SHR/SAR are the odd ones out among the binary opcodes: the EVM spec
+/// puts the shift amount on top of stack and the value being shifted
+/// underneath it (`result = value << shift`), the reverse of `lower_binary`'s
+/// "first-popped is the left-hand operand" rule.
+pub(crate) fn lower_shift(symbol_table: &mut SymbolTable, operator: Operator) -> Vec<Instruction> {
+    let shift = stack_pop(symbol_table);
+    let value = stack_pop(symbol_table);
+
+    let x = symbol_table.temporary(None).with_provenance(Provenance::Computed);
+    let compute = Instruction::BinaryAssign {
+        x,
+        y: value.load.target_address(),
+        operator,
+        z: shift.load.target_address(),
+    };
+
+    let push = stack_push(symbol_table, x);
+
+    vec![
+        shift.decrement,
+        shift.load,
+        value.decrement,
+        value.load,
+        compute,
+        push.assign,
+        push.increment,
+    ]
+}
+
+/// Pop `y`, compute `operator y` into a fresh temporary and push the result.
+pub(crate) fn lower_unary(symbol_table: &mut SymbolTable, operator: Operator) -> Vec<Instruction> {
+    let y = stack_pop(symbol_table);
+
+    let x = symbol_table.temporary(None).with_provenance(Provenance::Computed);
+    let compute = Instruction::UnaryAssign {
+        x,
+        operator,
+        y: y.load.target_address(),
+    };
+
+    let push = stack_push(symbol_table, x);
+
+    vec![y.decrement, y.load, compute, push.assign, push.increment]
+}
+
+/// Pop `a`, `b`, `n` and compute `(a outer b) inner n` into a fresh temporary,
+/// then push the result.
+///
+/// ADDMOD and MULMOD are the only ternary EVM opcodes; rather than add a
+/// dedicated three-operand instruction form we expand them into the two
+/// `BinaryAssign`s they are defined as.
+fn lower_ternary(symbol_table: &mut SymbolTable, outer: Operator, inner: Operator) -> Vec<Instruction> {
+    let a = stack_pop(symbol_table);
+    let b = stack_pop(symbol_table);
+    let n = stack_pop(symbol_table);
+
+    let partial = symbol_table.temporary(None).with_provenance(Provenance::Computed);
+    let outer_compute = Instruction::BinaryAssign {
+        x: partial,
+        y: a.load.target_address(),
+        operator: outer,
+        z: b.load.target_address(),
+    };
+
+    let x = symbol_table.temporary(None).with_provenance(Provenance::Computed);
+    let inner_compute = Instruction::BinaryAssign {
+        x,
+        y: partial,
+        operator: inner,
+        z: n.load.target_address(),
+    };
+
+    let push = stack_push(symbol_table, x);
+
+    vec![
+        a.decrement,
+        a.load,
+        b.decrement,
+        b.load,
+        n.decrement,
+        n.load,
+        outer_compute,
+        inner_compute,
+        push.assign,
+        push.increment,
+    ]
+}
+
+/// The highest number of elements the EVM allows on the stack at once.
+const STACK_LIMIT: u64 = 1024;
+
+/// The number of elements an opcode pops and pushes, used to guard against
+/// stack underflow/overflow before it executes.
+///
+/// Mirrors the `has(no_of_elems)` precondition the reference interpreter
+/// checks before every opcode. Opcodes not handled by `translate` have no
+/// stack effect here and need no guard.
+fn stack_effect(opcode: &EvmInstruction) -> (u64, u64) {
     use EvmInstruction::*;
     match opcode {
+        ADD | MUL | SUB | DIV | SDIV | MOD | SMOD | EXP | SIGNEXTEND | LT | GT | SLT | SGT
+        | EQ | AND | OR | XOR | BYTE | SHL | SHR | SAR => (2, 1),
+
+        ISZERO | NOT => (1, 1),
+
+        ADDMOD | MULMOD => (3, 1),
+
+        POP => (1, 0),
+
+        PUSH(_) => (0, 1),
+
+        DUP(n) => (u64::from(*n), u64::from(*n) + 1),
+
+        SWAP(n) => (u64::from(*n) + 1, u64::from(*n) + 1),
+
+        MSTORE => (2, 0),
+
+        JUMP => (1, 0),
+
+        JUMPI => (2, 0),
+
+        RETURN => (2, 0),
+
+        CALLDATACOPY => (3, 0),
+
+        CALLDATALOAD => (1, 1),
+
+        _ => (0, 0),
+    }
+}
+
+/// Prepend a stack underflow/overflow guard for an opcode that needs `pops`
+/// elements on the stack and leaves `pushes` in their place, a net change in
+/// stack height of `pushes` - `pops`.
+///
+/// Emits a `BinaryAssign`/`LessThat` comparing `StackHeight` against the
+/// required depth into a temporary, then a `ConditionalBranch` to
+/// `Global::Revert` when the comparison fails; and, for opcodes that net-push,
+/// the same pattern checking against the 1024-element ceiling.
+fn stack_guard(symbol_table: &mut SymbolTable, pops: u64, pushes: u64) -> Vec<Instruction> {
+    let mut guard = Vec::new();
+
+    if pops > 0 {
+        let underflows = symbol_table.temporary(None);
+        guard.push(Instruction::BinaryAssign {
+            x: underflows,
+            y: symbol_table.global(Global::StackHeight),
+            operator: Operator::LessThat,
+            z: symbol_table.constant(U256::from(pops), Some(Type::Int(4))),
+        });
+        guard.push(Instruction::ConditionalBranch {
+            condition: underflows,
+            target: symbol_table.global(Global::Revert),
+        });
+    }
+
+    if pushes > pops {
+        let net_push = pushes - pops;
+
+        let height_after = symbol_table.temporary(None);
+        guard.push(Instruction::BinaryAssign {
+            x: height_after,
+            y: symbol_table.global(Global::StackHeight),
+            operator: Operator::Add,
+            z: symbol_table.constant(U256::from(net_push), Some(Type::Int(4))),
+        });
+
+        let overflows = symbol_table.temporary(None);
+        guard.push(Instruction::BinaryAssign {
+            x: overflows,
+            y: height_after,
+            operator: Operator::GreaterThan,
+            z: symbol_table.constant(U256::from(STACK_LIMIT), Some(Type::Int(4))),
+        });
+        guard.push(Instruction::ConditionalBranch {
+            condition: overflows,
+            target: symbol_table.global(Global::Revert),
+        });
+    }
+
+    guard
+}
+
+/// Lower an EVM instruction into corresponding 3AC instructions.
+///
+/// When `checked` is set, a stack underflow/overflow guard (see
+/// `stack_guard`) is emitted ahead of the opcode's own instructions. Callers
+/// that already trust their input (e.g. bytecode that passed static stack
+/// analysis) can pass `false` to skip it.
+pub fn translate(opcode: &EvmInstruction, symbol_table: &mut SymbolTable, checked: bool) -> Vec<Instruction> {
+    use EvmInstruction::*;
+
+    let mut instructions = if checked {
+        let (pops, pushes) = stack_effect(opcode);
+        stack_guard(symbol_table, pops, pushes)
+    } else {
+        Vec::new()
+    };
+
+    instructions.extend(match opcode {
         JUMPDEST => Vec::new(),
 
+        ADD => lower_binary(symbol_table, Operator::Add),
+        MUL => lower_binary(symbol_table, Operator::Mul),
+        SUB => lower_binary(symbol_table, Operator::Sub),
+        DIV => lower_binary(symbol_table, Operator::Div),
+        SDIV => lower_binary(symbol_table, Operator::SDiv),
+        MOD => lower_binary(symbol_table, Operator::Mod),
+        SMOD => lower_binary(symbol_table, Operator::SMod),
+        EXP => lower_binary(symbol_table, Operator::Exp),
+        SIGNEXTEND => lower_binary(symbol_table, Operator::SignExtend),
+
+        LT => lower_binary(symbol_table, Operator::LessThat),
+        GT => lower_binary(symbol_table, Operator::GreaterThan),
+        SLT => lower_binary(symbol_table, Operator::SignedLessThan),
+        SGT => lower_binary(symbol_table, Operator::SignedGreaterThan),
+        EQ => lower_binary(symbol_table, Operator::Eq),
+        ISZERO => lower_unary(symbol_table, Operator::IsZero),
+
+        AND => lower_binary(symbol_table, Operator::And),
+        OR => lower_binary(symbol_table, Operator::Or),
+        XOR => lower_binary(symbol_table, Operator::Xor),
+        NOT => lower_unary(symbol_table, Operator::Not),
+        BYTE => lower_binary(symbol_table, Operator::Byte),
+        SHL => lower_shift(symbol_table, Operator::ShiftLeft),
+        SHR => lower_shift(symbol_table, Operator::ShiftRight),
+        SAR => lower_shift(symbol_table, Operator::ShiftArithmeticRight),
+
+        ADDMOD => lower_ternary(symbol_table, Operator::Add, Operator::Mod),
+        MULMOD => lower_ternary(symbol_table, Operator::Mul, Operator::Mod),
+
+        DUP(n) => {
+            let peek = stack_peek(symbol_table, *n);
+            let push = stack_push(symbol_table, peek.load.target_address());
+
+            vec![peek.index, peek.load, push.assign, push.increment]
+        }
+
+        SWAP(n) => stack_swap(symbol_table, *n),
+
         PUSH(bytes) => {
             let type_hint = Some(Type::Bytes(bytes.len()));
             let value = symbol_table.constant(U256::from_big_endian(bytes), type_hint);
@@ -209,7 +591,10 @@ pub fn translate(opcode: &EvmInstruction, symbol_table: &mut SymbolTable) -> Vec
             vec![push.assign, push.increment]
         }
 
-        POP => vec![decrement_stack_height(symbol_table)],
+        POP => {
+            symbol_table.shadow_pop();
+            vec![decrement_stack_height(symbol_table)]
+        }
 
         MSTORE => {
             let offset = stack_pop(symbol_table);
@@ -240,6 +625,24 @@ pub fn translate(opcode: &EvmInstruction, symbol_table: &mut SymbolTable) -> Vec
             vec![target.decrement, target.load, jump]
         }
 
+        JUMPI => {
+            let target = stack_pop(symbol_table);
+            let condition = stack_pop(symbol_table);
+
+            let branch = Instruction::ConditionalBranch {
+                condition: condition.load.target_address(),
+                target: target.load.target_address(),
+            };
+
+            vec![
+                target.decrement,
+                target.load,
+                condition.decrement,
+                condition.load,
+                branch,
+            ]
+        }
+
         RETURN => {
             let offset = stack_pop(symbol_table);
             let size = stack_pop(symbol_table);
@@ -289,7 +692,7 @@ pub fn translate(opcode: &EvmInstruction, symbol_table: &mut SymbolTable) -> Vec
             let index = stack_pop(symbol_table);
 
             let value = Instruction::IndexedCopy {
-                x: symbol_table.temporary(None),
+                x: symbol_table.temporary(None).with_provenance(Provenance::CallData),
                 y: symbol_table.global(Global::CallData),
                 index: index.load.target_address(),
             };
@@ -307,7 +710,9 @@ pub fn translate(opcode: &EvmInstruction, symbol_table: &mut SymbolTable) -> Vec
 
         //_ => todo!("{opcode}"),
         _ => Vec::new(),
-    }
+    });
+
+    instructions
 }
 
 #[cfg(test)]
@@ -327,7 +732,7 @@ mod tests {
         let mut symbol_table = Default::default();
 
         let opcode = bytecode::Instruction::PUSH(vec![0x01]);
-        let result = super::translate(&opcode, &mut symbol_table);
+        let result = super::translate(&opcode, &mut symbol_table, false);
 
         let expected = vec![
             Instruction::IndexedAssign {
@@ -335,16 +740,19 @@ mod tests {
                     address: Address::Label(Global::Stack),
                     type_hint: Type::Word,
                     kind: Global::Stack.kind(),
+                    provenance: None,
                 },
                 index: Symbol {
                     address: Address::Label(Global::StackHeight),
                     type_hint: Type::Int(4),
                     kind: Global::StackHeight.kind(),
+                    provenance: None,
                 },
                 y: Symbol {
                     address: Address::Constant(U256::one()),
                     type_hint: Type::Bytes(1),
                     kind: Kind::Value,
+                    provenance: None,
                 },
             },
             Instruction::BinaryAssign {
@@ -352,21 +760,303 @@ mod tests {
                     address: Address::Label(Global::StackHeight),
                     type_hint: Type::Int(4),
                     kind: Global::StackHeight.kind(),
+                    provenance: None,
                 },
                 y: Symbol {
                     address: Address::Label(Global::StackHeight),
                     type_hint: Type::Int(4),
                     kind: Global::StackHeight.kind(),
+                    provenance: None,
                 },
                 operator: Operator::Add,
                 z: Symbol {
                     address: Address::Constant(U256::one()),
                     type_hint: Type::Int(4),
                     kind: Kind::Value,
+                    provenance: None,
                 },
             },
         ];
 
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn lower_sub_works() {
+        let mut symbol_table = Default::default();
+
+        let opcode = bytecode::Instruction::SUB;
+        let result = super::translate(&opcode, &mut symbol_table, false);
+
+        // SUB pops `a` (top) then `b` and must compute `a - b`, not `b - a`.
+        let compute = result
+            .iter()
+            .find_map(|instruction| match instruction {
+                Instruction::BinaryAssign {
+                    operator: Operator::Sub,
+                    y,
+                    z,
+                    ..
+                } => Some((*y, *z)),
+                _ => None,
+            })
+            .expect("SUB should lower to a BinaryAssign with the Sub operator");
+
+        let loads: Vec<_> = result
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::IndexedCopy { x, .. } => Some(*x),
+                _ => None,
+            })
+            .collect();
+
+        assert_ne!(compute.0, compute.1);
+        assert_eq!(compute.0, loads[0], "y (a) should be the first value popped");
+        assert_eq!(compute.1, loads[1], "z (b) should be the second value popped");
+    }
+
+    #[test]
+    fn lower_shl_keeps_the_shift_amount_and_value_in_evm_order() {
+        let mut symbol_table = Default::default();
+
+        // PUSH value; PUSH shift; SHL — the shift amount ends up on top, but
+        // the computed `BinaryAssign` must read `y = value`, `z = shift`
+        // (EVM's `result = value << shift`), not the other way around.
+        let mut program = Vec::new();
+        program.extend(super::translate(
+            &bytecode::Instruction::PUSH(vec![0x04]),
+            &mut symbol_table,
+            false,
+        ));
+        program.extend(super::translate(
+            &bytecode::Instruction::PUSH(vec![0x01]),
+            &mut symbol_table,
+            false,
+        ));
+        program.extend(super::translate(&bytecode::Instruction::SHL, &mut symbol_table, false));
+
+        let compute = program
+            .iter()
+            .find_map(|instruction| match instruction {
+                Instruction::BinaryAssign {
+                    operator: Operator::ShiftLeft,
+                    y,
+                    z,
+                    ..
+                } => Some((*y, *z)),
+                _ => None,
+            })
+            .expect("SHL should lower to a BinaryAssign with the ShiftLeft operator");
+
+        // SHL's own two pops are the only IndexedCopys in this program (the
+        // PUSHes themselves only write, never read): the first one loaded is
+        // the top-of-stack shift amount, the second is the value underneath.
+        let loads: Vec<_> = program
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::IndexedCopy { x, .. } => Some(*x),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(loads.len(), 2, "SHL should pop exactly the shift amount and the value");
+        assert_eq!(compute.1, loads[0], "z (shift) should be the first value popped");
+        assert_eq!(compute.0, loads[1], "y (value) should be the second value popped");
+    }
+
+    #[test]
+    fn lower_jumpi_pops_target_then_condition_and_branches() {
+        let mut symbol_table = Default::default();
+
+        let opcode = bytecode::Instruction::JUMPI;
+        let result = super::translate(&opcode, &mut symbol_table, false);
+
+        assert!(result
+            .iter()
+            .any(|instruction| matches!(instruction, Instruction::ConditionalBranch { .. })));
+
+        // Two pops (target, condition): each is a decrement followed by a
+        // load, same bookkeeping as every other stack_pop consumer.
+        let decrements = result
+            .iter()
+            .filter(|instruction| match instruction {
+                Instruction::BinaryAssign { x, operator: Operator::Sub, .. } => {
+                    x.address == Address::Label(Global::StackHeight)
+                }
+                _ => false,
+            })
+            .count();
+
+        assert_eq!(decrements, 2);
+
+        // The first value popped is the target, the second the condition.
+        let loads: Vec<_> = result
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::IndexedCopy { x, .. } => Some(*x),
+                _ => None,
+            })
+            .collect();
+        let branch = result.iter().find_map(|instruction| match instruction {
+            Instruction::ConditionalBranch { condition, target } => Some((*condition, *target)),
+            _ => None,
+        });
+
+        assert_eq!(branch, Some((loads[1], loads[0])));
+    }
+
+    #[test]
+    fn checked_jumpi_emits_an_underflow_guard_for_both_pops() {
+        let mut symbol_table = Default::default();
+
+        let opcode = bytecode::Instruction::JUMPI;
+        let result = super::translate(&opcode, &mut symbol_table, true);
+
+        let guard = result.iter().find_map(|instruction| match instruction {
+            Instruction::BinaryAssign {
+                operator: Operator::LessThat,
+                z,
+                ..
+            } => Some(*z),
+            _ => None,
+        });
+
+        assert_eq!(guard.map(|z| z.address), Some(Address::Constant(U256::from(2u64))));
+    }
+
+    #[test]
+    fn lower_dup1_leaves_stack_height_net_positive() {
+        let mut symbol_table = Default::default();
+
+        let opcode = bytecode::Instruction::DUP(1);
+        let result = super::translate(&opcode, &mut symbol_table, false);
+
+        // DUP1 peeks the top element and pushes a copy: one IndexedCopy from
+        // Global::Stack followed by one IndexedAssign back into it.
+        let reads_stack = result
+            .iter()
+            .any(|instruction| matches!(instruction, Instruction::IndexedCopy { .. }));
+        let writes_stack = result
+            .iter()
+            .any(|instruction| matches!(instruction, Instruction::IndexedAssign { .. }));
+
+        assert!(reads_stack);
+        assert!(writes_stack);
+
+        let height_delta: i32 = result
+            .iter()
+            .map(|instruction| match instruction {
+                Instruction::BinaryAssign { x, operator: Operator::Add, .. }
+                    if x.address == Address::Label(Global::StackHeight) => 1,
+                Instruction::BinaryAssign { x, operator: Operator::Sub, .. }
+                    if x.address == Address::Label(Global::StackHeight) => -1,
+                _ => 0,
+            })
+            .sum();
+
+        assert_eq!(height_delta, 1);
+    }
+
+    #[test]
+    fn lower_swap1_does_not_change_stack_height() {
+        let mut symbol_table = Default::default();
+
+        let opcode = bytecode::Instruction::SWAP(1);
+        let result = super::translate(&opcode, &mut symbol_table, false);
+
+        let touches_stack_height = result.iter().any(|instruction| match instruction {
+            Instruction::BinaryAssign { x, .. } => x.address == Address::Label(Global::StackHeight),
+            _ => false,
+        });
+
+        assert!(!touches_stack_height);
+    }
+
+    #[test]
+    fn checked_pop_emits_an_underflow_guard_before_the_opcode() {
+        let mut symbol_table = Default::default();
+
+        let opcode = bytecode::Instruction::POP;
+        let result = super::translate(&opcode, &mut symbol_table, true);
+
+        let guards_before_decrement = matches!(
+            result.first(),
+            Some(Instruction::BinaryAssign {
+                operator: Operator::LessThat,
+                ..
+            })
+        ) && matches!(
+            result.get(1),
+            Some(Instruction::ConditionalBranch { .. })
+        );
+
+        assert!(guards_before_decrement);
+    }
+
+    #[test]
+    fn unchecked_pop_emits_no_guard() {
+        let mut symbol_table = Default::default();
+
+        let opcode = bytecode::Instruction::POP;
+        let result = super::translate(&opcode, &mut symbol_table, false);
+
+        assert!(!result
+            .iter()
+            .any(|instruction| matches!(instruction, Instruction::ConditionalBranch { .. })));
+    }
+
+    #[test]
+    fn lower_calldataload_tags_the_loaded_value_with_calldata_provenance() {
+        use crate::symbol::Provenance;
+
+        let mut symbol_table = Default::default();
+
+        let opcode = bytecode::Instruction::CALLDATALOAD;
+        let result = super::translate(&opcode, &mut symbol_table, false);
+
+        let tagged = result.iter().any(|instruction| match instruction {
+            Instruction::IndexedCopy { x, y, .. } => {
+                matches!(x.provenance, Some(Provenance::CallData))
+                    && y.address == Address::Label(Global::CallData)
+            }
+            _ => false,
+        });
+
+        assert!(tagged);
+    }
+
+    #[test]
+    fn calldata_provenance_survives_a_stack_round_trip_into_mstore() {
+        use crate::symbol::Provenance;
+
+        let mut symbol_table = Default::default();
+
+        // PUSH offset; CALLDATALOAD; PUSH mem_offset; MSTORE — the loaded
+        // value is popped back off the stack by MSTORE, after a second PUSH
+        // has gone on top of it since CALLDATALOAD tagged it.
+        let mut program = Vec::new();
+        program.extend(super::translate(
+            &bytecode::Instruction::PUSH(vec![0x00]),
+            &mut symbol_table,
+            false,
+        ));
+        program.extend(super::translate(&bytecode::Instruction::CALLDATALOAD, &mut symbol_table, false));
+        program.extend(super::translate(
+            &bytecode::Instruction::PUSH(vec![0x20]),
+            &mut symbol_table,
+            false,
+        ));
+        program.extend(super::translate(&bytecode::Instruction::MSTORE, &mut symbol_table, false));
+
+        let tagged = program.iter().any(|instruction| match instruction {
+            Instruction::IndexedAssign {
+                y,
+                x,
+                ..
+            } => matches!(y.provenance, Some(Provenance::CallData)) && x.address == Address::Label(Global::Memory),
+            _ => false,
+        });
+
+        assert!(tagged);
+    }
 }
@@ -0,0 +1,189 @@
+use primitive_types::U256;
+
+/// Where a `Symbol`'s address lives.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Address {
+    /// A named global location (the stack, its height, memory, ...).
+    Label(Global),
+    /// A compile-time constant value.
+    Constant(U256),
+    /// A fresh, uniquely-numbered temporary.
+    Temporary(u32),
+}
+
+/// The well-known global locations the 3AC operates on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Global {
+    Stack,
+    StackHeight,
+    Memory,
+    MemoryCopy,
+    CallData,
+    Return,
+    Revert,
+}
+
+impl Global {
+    /// The `Kind` of value this global holds.
+    pub fn kind(self) -> Kind {
+        match self {
+            Global::Stack | Global::Memory | Global::CallData => Kind::Array,
+            Global::StackHeight => Kind::Counter,
+            Global::MemoryCopy | Global::Return | Global::Revert => Kind::Procedure,
+        }
+    }
+
+    /// The type hint for a `Symbol` referring directly to this global.
+    fn type_hint(self) -> Type {
+        match self {
+            Global::StackHeight => Type::Int(4),
+            _ => Type::Word,
+        }
+    }
+}
+
+/// The shape of value a `Symbol` refers to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Value,
+    Array,
+    Counter,
+    Procedure,
+}
+
+/// A type hint attached to a `Symbol`, used for sizing and display only.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Type {
+    Word,
+    Int(usize),
+    Bytes(usize),
+}
+
+/// Where a value's `Symbol` was materialized from.
+///
+/// Pure metadata: it does not affect program semantics, so it is excluded
+/// from `Symbol`'s `PartialEq`. Later alias/taint passes can use it to, for
+/// instance, fold constant-indexed calldata loads or prove two memory
+/// accesses cannot alias.
+#[derive(Clone, Copy, Debug)]
+pub enum Provenance {
+    CallData,
+    Memory,
+    Storage,
+    Computed,
+    Const,
+}
+
+/// A reference to a 3AC value: a stack slot, a global, a constant, or a
+/// temporary.
+#[derive(Clone, Copy, Debug)]
+pub struct Symbol {
+    pub address: Address,
+    pub type_hint: Type,
+    pub kind: Kind,
+    pub provenance: Option<Provenance>,
+}
+
+impl Symbol {
+    /// Tag this symbol with where it was materialized from.
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = Some(provenance);
+        self
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address && self.type_hint == other.type_hint && self.kind == other.kind
+    }
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.address {
+            Address::Label(global) => write!(f, "{global:?}"),
+            Address::Constant(value) => write!(f, "{value}"),
+            Address::Temporary(id) => write!(f, "t{id}"),
+        }
+    }
+}
+
+/// Allocates constants, globals, and fresh temporaries, handing out unique
+/// `Symbol`s for each.
+///
+/// Also shadows `Global::Stack` at IR-construction time: `stack_push`
+/// records the provenance of each pushed value here, so a later
+/// `stack_pop`/`stack_peek` can tag the temporary it loads with the
+/// provenance of whatever is actually sitting in that slot, instead of
+/// starting over with `None`.
+#[derive(Default)]
+pub struct SymbolTable {
+    next_temporary: u32,
+    stack_shadow: Vec<Option<Provenance>>,
+}
+
+impl SymbolTable {
+    /// A fresh, uniquely-numbered temporary.
+    pub fn temporary(&mut self, type_hint: Option<Type>) -> Symbol {
+        let id = self.next_temporary;
+        self.next_temporary += 1;
+
+        Symbol {
+            address: Address::Temporary(id),
+            type_hint: type_hint.unwrap_or(Type::Word),
+            kind: Kind::Value,
+            provenance: None,
+        }
+    }
+
+    /// A reference to a well-known global location.
+    pub fn global(&mut self, global: Global) -> Symbol {
+        Symbol {
+            address: Address::Label(global),
+            type_hint: global.type_hint(),
+            kind: global.kind(),
+            provenance: None,
+        }
+    }
+
+    /// A compile-time constant value.
+    pub fn constant(&mut self, value: U256, type_hint: Option<Type>) -> Symbol {
+        Symbol {
+            address: Address::Constant(value),
+            type_hint: type_hint.unwrap_or(Type::Word),
+            kind: Kind::Value,
+            provenance: Some(Provenance::Const),
+        }
+    }
+
+    /// Record the provenance of a value being pushed onto `Global::Stack`.
+    pub(crate) fn shadow_push(&mut self, provenance: Option<Provenance>) {
+        self.stack_shadow.push(provenance);
+    }
+
+    /// Remove and return the provenance of the value on top of
+    /// `Global::Stack`, or `None` if nothing was tracked for that slot.
+    pub(crate) fn shadow_pop(&mut self) -> Option<Provenance> {
+        self.stack_shadow.pop().flatten()
+    }
+
+    /// The provenance of the element `n_from_top` slots below the top of
+    /// `Global::Stack` (0-indexed), without removing it.
+    pub(crate) fn shadow_peek(&self, n_from_top: usize) -> Option<Provenance> {
+        let len = self.stack_shadow.len();
+        if n_from_top >= len {
+            return None;
+        }
+
+        self.stack_shadow[len - 1 - n_from_top]
+    }
+
+    /// Swap the provenance of the top of `Global::Stack` with the one
+    /// `n_from_top` slots below it (0-indexed), mirroring SWAP1..SWAP16.
+    pub(crate) fn shadow_swap(&mut self, n_from_top: usize) {
+        let len = self.stack_shadow.len();
+        if n_from_top < len {
+            self.stack_shadow.swap(len - 1, len - 1 - n_from_top);
+        }
+    }
+}